@@ -3,7 +3,7 @@
 //!
 //! ## Introduction
 //!
-//! This crate allows to generate 4-stroke engine waveforms for direct writing on GPIO as bit mask.
+//! This crate allows to generate 2-stroke and 4-stroke engine waveforms for direct writing on GPIO as bit mask.
 //!
 //! The goal is to generate a pulse train with the following information:
 //! * crankshaft wheel signal
@@ -12,7 +12,9 @@
 //!
 //! ## Technical information
 //!
-//! The pulse train buffer is an array of integers (generally u8, u16 or u32, depending on the GPIO register width) with length 7200. An array has been used
+//! The pulse train buffer is an array of integers (generally u8, u16 or u32, depending on the GPIO register width) whose length `LEN` is a const generic,
+//! trading RAM for angular resolution (for a four-stroke cycle, 7200 gives the default 0.1°/step, 3600 gives 0.2°/step, 14400 gives 0.05°/step, etc.;
+//! a two-stroke buffer spans a single 360° revolution, so each step is half as wide). An array has been used
 //! in order to be compatible with DMA (Direct Memory Access) mechanisms. The goal
 //! of this crate is really to create crank/cam/tdc generators for ECU development purpose.
 //!
@@ -40,7 +42,6 @@
 //!
 //! The following engines can not be generated at the moment:
 //! * Engines with more than 6 cylinders
-//! * Asymmetrical engines (TDCs are not spaced evenly)
 //! * The concept uses a relatively high amount of RAM. But with the use of appropriate DMA and timers, the pulse train generation should not even require CPU processing.
 //!
 
@@ -176,7 +177,74 @@ impl CrkType {
     }
 }
 
-/// Camshaft wheel configuration
+/// Engine operation cycle, i.e. how many crank revolutions make up one full engine cycle
+/// (and therefore one pulse-train buffer). rusEFI distinguishes `TWO_STROKE` (360°, one crank
+/// revolution per cycle) from `FOUR_STROKE_ENGINE_CYCLE` (720°, two crank revolutions per cycle)
+pub enum OperationMode {
+    /// 360° cycle: one crank revolution per engine cycle. The camshaft track is typically
+    /// absent on two-stroke engines
+    TwoStroke,
+    /// 720° cycle: two crank revolutions per engine cycle
+    FourStroke,
+}
+
+impl OperationMode {
+    /// Returns the number of crank revolutions spanned by one full engine cycle
+    pub const fn revs_per_cycle(&self) -> usize {
+        match *self {
+            OperationMode::TwoStroke => 1,
+            OperationMode::FourStroke => 2,
+        }
+    }
+}
+
+/// Maximum number of independent missing-teeth gaps a [`CrkPattern`] can describe.
+pub const MAX_CRK_GAPS: usize = 4;
+
+/// Describes an arbitrary crankshaft wheel as a total tooth count plus a list of gaps
+/// (missing-teeth regions), each given as `(start tooth index, number of missing teeth)`.
+/// Generalizes [`CrkType`]'s single end-of-wheel gap to wheels with several gaps distributed
+/// around the circumference. Unused gap slots are marked with a missing count of 0
+pub struct CrkPattern {
+    /// Total number of nominal tooth slots on the wheel, missing teeth included
+    pub total_teeth: usize,
+    /// Missing-teeth gaps, as `(start tooth index, missing count)`. Unused slots are `(0, 0)`
+    pub gaps: [(u16, u8); MAX_CRK_GAPS],
+    /// Level seen when starting a rotation from angle 0
+    pub first_level: Level,
+}
+
+impl CrkPattern {
+    /// Builds the single-gap pattern equivalent to a legacy [`CrkType`], with its gap placed at
+    /// the end of the wheel (the last `nr_of_missing_teeth` tooth slots)
+    pub const fn from_crk_type(t: &CrkType) -> Self {
+        let total_teeth = t.nr_of_teeth();
+        let missing = t.nr_of_missing_teeth();
+
+        let mut gaps = [(0u16, 0u8); MAX_CRK_GAPS];
+        gaps[0] = ((total_teeth - missing) as u16, missing as u8);
+
+        CrkPattern {
+            total_teeth,
+            gaps,
+            first_level: t.first_level(),
+        }
+    }
+
+    /// Returns whether the nominal tooth slot `tooth_idx` falls inside one of this pattern's gaps
+    pub fn is_in_gap(&self, tooth_idx: usize) -> bool {
+        self.gaps.iter().any(|&(start, count)| {
+            count > 0 && tooth_idx >= start as usize && tooth_idx < start as usize + count as usize
+        })
+    }
+}
+
+/// Maximum number of independent camshaft tracks an engine configuration can carry
+pub const MAX_CAM_TRACKS: usize = 2;
+
+/// Configuration of a single camshaft track. Most engines only need one; phase-sensing setups
+/// with several coded cam wheels need more. `EngCfg` carries `MAX_CAM_TRACKS` of these, each
+/// driven independently and written to its own bitmask
 pub struct Cam {
     /// Level when first crankshaft gap is met
     pub first_level: Level,
@@ -184,29 +252,194 @@ pub struct Cam {
     pub ev_angles: [i16; 20],
 }
 
+impl Cam {
+    /// A track carrying no edges at all, for engines that only need fewer than
+    /// [`MAX_CAM_TRACKS`] tracks
+    pub const UNUSED: Cam = Cam {
+        first_level: Level::Low,
+        ev_angles: [-1; 20],
+    };
+}
+
+/// Conventional sentinel marking an unused slot in [`EngCfg::tdc_angles`]. Any negative value is
+/// treated as unused, not just this one
+pub const TDC_UNUSED: i16 = -1;
+
+/// Errors returned by [`EngCfg::gen_pulse_train`] when the configuration cannot be generated
+/// into the given buffer
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum GenError {
+    /// A TDC angle falls outside the `[0, LEN)` buffer range
+    TdcOutOfRange {
+        /// Index of the offending cylinder in `tdc_angles`
+        cyl: usize,
+    },
+    /// A camshaft edge angle is outside `[0, LEN)`, not strictly greater than the previous edge
+    /// angle on the same track, or a valid edge follows an unused (negative) slot
+    CamEdgeInvalid {
+        /// Index of the offending track in `cam`
+        track: usize,
+        /// Index of the offending edge in the track's `ev_angles`
+        index: usize,
+    },
+    /// The crankshaft gap geometry is inconsistent with the configured tooth count: `total_teeth`
+    /// is 0, a gap extends past `total_teeth`, one revolution of the buffer holds fewer elements
+    /// than `total_teeth`, or the wheel's total angular span (`total_teeth * tooth_angle`)
+    /// deviates from the revolution span by more than 25%
+    InconsistentCrkGeometry,
+}
+
 /// Engine configuration
 pub struct EngCfg {
-    /// Camshaft wheel configuration
-    pub cam: Cam,
-    /// Crankshaft type configuration
-    pub crk: CrkType,
-    /// Angle from reference (crank gap) to first Top-Dead-Center (TDC): DEG_S16_DEC1
-    pub ref_to_tdc0: i16,
-    /// Number of cylinders, used for TDC generation
-    pub nr_of_cyl: CylNr,
+    /// Camshaft wheel configuration, one entry per independent cam track
+    pub cam: [Cam; MAX_CAM_TRACKS],
+    /// Crankshaft wheel configuration
+    pub crk: CrkPattern,
+    /// Absolute angle of each cylinder's Top-Dead-Center (TDC), index 0 is TDC0, from the crank
+    /// reference (the first crank gap), in array-index units of the pulse-train buffer (tenths of a
+    /// degree for a four-stroke cycle in a 7200-element buffer). Unused cylinder slots are negative, by
+    /// convention [`TDC_UNUSED`]. Engines with evenly-spaced TDCs can build this with
+    /// [`EngCfg::even_tdc_angles`]; engines with an uneven firing order (V6/V8) should fill it
+    /// directly
+    pub tdc_angles: [i16; 6],
+    /// Width, in tenths of percent of one tooth pitch (0..=1000), of the last part of each
+    /// crankshaft tooth pitch, which sits at the level opposite to the wheel's `first_level` (the
+    /// gap level). 500 reproduces a symmetrical 50%/50% mark-space ratio. On a wheel whose
+    /// `first_level` is `Low` this is the high tooth mark, so lower values model a narrower tooth
+    /// (as seen on some VR/Hall trigger wheels), higher values a wider one; on a wheel whose
+    /// `first_level` is `High` it is the low part of the pitch.
+    pub tooth_width: u16,
+    /// Engine operation cycle (two-stroke or four-stroke), used to derive how many crank
+    /// revolutions the pulse-train buffer spans
+    pub mode: OperationMode,
 }
 
 impl EngCfg {
+    /// Builds the evenly-spaced `tdc_angles` of an engine whose `nr_of_cyl` cylinders fire at a
+    /// constant `ref_to_tdc0 + cyl * (cycle_span / nr_of_cyl)` interval, where `cycle_span` is the
+    /// full engine cycle spanned by one pulse-train buffer: `rev_span * mode.revs_per_cycle()`.
+    /// `rev_span` must match the one [`EngCfg::gen_pulse_train`] derives from the buffer it is
+    /// fed into, i.e. `LEN / mode.revs_per_cycle()` — not `LEN` itself. This is a convenience
+    /// constructor for the common, symmetrically-spaced firing order
+    pub const fn even_tdc_angles(
+        ref_to_tdc0: i16,
+        nr_of_cyl: &CylNr,
+        rev_span: usize,
+        mode: &OperationMode,
+    ) -> [i16; 6] {
+        let n = nr_of_cyl.val();
+        let cycle_span = rev_span * mode.revs_per_cycle();
+        let tdc_to_tdc = (cycle_span / n) as i16;
+
+        let mut angles = [TDC_UNUSED; 6];
+        let mut cyl = 0;
+        while cyl < n {
+            angles[cyl] = ref_to_tdc0 + (cyl as i16) * tdc_to_tdc;
+            cyl += 1;
+        }
+        angles
+    }
+
+    /// Returns the period, in nanoseconds, of the timer tick that must drive a `buffer_len`-element
+    /// pulse train generated by [`EngCfg::gen_pulse_train`] for the engine to spin at `rpm`.
+    ///
+    /// One full engine cycle spans `mode.revs_per_cycle()` crank revolutions, which at `rpm` takes
+    /// `mode.revs_per_cycle() * 60_000_000_000 / rpm` nanoseconds; dividing that by `buffer_len`
+    /// gives the per-element period the output timer must be reconfigured with whenever engine
+    /// speed changes. `rpm` of 0 is clamped to 1 rather than panicking
+    pub const fn step_period_ns(rpm: u32, buffer_len: usize, mode: &OperationMode) -> u32 {
+        let rpm = if rpm == 0 { 1 } else { rpm };
+        let cycle_ns = (mode.revs_per_cycle() as u64 * 60_000_000_000) / rpm as u64;
+        (cycle_ns / buffer_len as u64) as u32
+    }
+
+    /// Computes the crankshaft level at a given array index (angle).
+    ///
+    /// Within one crank revolution (`rev_span` array-index units), each nominal tooth `i`
+    /// carries the wheel's `first_level` for its first part and the opposite level for its last
+    /// `tooth_width` tenths-of-percent, i.e. the edge to the opposite level sits at
+    /// `oneTooth * (i + 1 - tooth_width / 1000)` and the edge back to `first_level` at
+    /// `oneTooth * (i + 1)` (a rising then a falling edge when `first_level` is `Low`). Tooth
+    /// slots falling inside one of the pattern's gaps are held at the gap level
+    /// (`!first_level`) instead.
+    fn crk_level_at(&self, angle: usize, crk_tooth_angle: usize, rev_span: usize) -> Level {
+        let rev_angle = angle % rev_span;
+        let tooth_idx = rev_angle / crk_tooth_angle;
+
+        if self.crk.is_in_gap(tooth_idx) {
+            return !self.crk.first_level;
+        }
+
+        let tooth_end = crk_tooth_angle * (tooth_idx + 1);
+        let mark_width = (crk_tooth_angle * self.tooth_width.min(1000) as usize) / 1000;
+        let rise = tooth_end.saturating_sub(mark_width).max(tooth_end - crk_tooth_angle);
+
+        if rev_angle < rise {
+            self.crk.first_level
+        } else {
+            !self.crk.first_level
+        }
+    }
+
+    /// Checks that this configuration can be generated into a `LEN`-element buffer without
+    /// panicking: TDC angles and cam edges must land in `[0, LEN)`, cam edges must be
+    /// monotonically increasing with unused (negative) slots trailing all valid ones, and the
+    /// crankshaft gap geometry must be consistent with the configured tooth count and revolution
+    /// span
+    fn validate<const LEN: usize>(&self, rev_span: usize, crk_tooth_angle: usize) -> Result<(), GenError> {
+        for (cyl, &angle) in self.tdc_angles.iter().enumerate() {
+            if angle >= 0 && angle as usize >= LEN {
+                return Err(GenError::TdcOutOfRange { cyl });
+            }
+        }
+
+        for (track, cam) in self.cam.iter().enumerate() {
+            let mut prev = i16::MIN;
+            let mut seen_unused = false;
+            for (index, &edge) in cam.ev_angles.iter().enumerate() {
+                if edge < 0 {
+                    seen_unused = true;
+                    continue;
+                }
+                if seen_unused || edge as usize >= LEN || edge <= prev {
+                    return Err(GenError::CamEdgeInvalid { track, index });
+                }
+                prev = edge;
+            }
+        }
+
+        for &(start, count) in self.crk.gaps.iter() {
+            if count > 0 && start as usize + count as usize > self.crk.total_teeth {
+                return Err(GenError::InconsistentCrkGeometry);
+            }
+        }
+
+        let wheel_span = crk_tooth_angle * self.crk.total_teeth;
+        let tolerance = rev_span / 4;
+        if crk_tooth_angle == 0 || wheel_span.abs_diff(rev_span) > tolerance {
+            return Err(GenError::InconsistentCrkGeometry);
+        }
+
+        Ok(())
+    }
+
     /// Arguments:
-    /// * pt: output argument, pulse train generated from engine configuration for waveform generation
-    /// * cam_msk: bitmask indicating camshaft signal position in bitfield
+    /// * pt: output argument, pulse train generated from engine configuration for waveform generation. `LEN` trades RAM for angular resolution (for a four-stroke cycle, 7200 gives 0.1°/step, 3600 gives 0.2°/step, 14400 gives 0.05°/step, etc.; a two-stroke buffer spans one revolution, so each step is half as wide); `cam[..].ev_angles` and `tdc_angles` are expressed directly in array-index units of the chosen `LEN`, while `tooth_width` is a fraction of the tooth pitch
+    /// * cam_msk: bitmasks indicating each camshaft track's signal position in bitfield
     /// * crk_msk: bitmask indicating crankshaft signal position in bitfield
     /// * tdc_msk: bitmasks (starts with TDC0) indicating TDCs signal position in bitfield
     ///
     /// Returns:
     /// * Ok: generation has been achieved correctly
-    /// * Err: the buffer has not the minimal required length
-    pub fn gen_pulse_train<T>(&self, pt: &mut [T; 7200], cam_msk: T, crk_msk: T, tdc_msk: [T; 6])
+    /// * Err: the configuration is inconsistent with the chosen buffer length and could not be
+    ///   generated
+    pub fn gen_pulse_train<T, const LEN: usize>(
+        &self,
+        pt: &mut [T; LEN],
+        cam_msk: [T; MAX_CAM_TRACKS],
+        crk_msk: T,
+        tdc_msk: [T; 6],
+    ) -> Result<(), GenError>
     where
         T: Copy
             + Clone
@@ -216,65 +449,74 @@ impl EngCfg {
             + Not<Output = T>
             + BitOr<Output = T>,
     {
-        let mut idx_cam_edges = 0;
-        let mut cam_lvl = self.cam.first_level;
+        if self.crk.total_teeth == 0 {
+            return Err(GenError::InconsistentCrkGeometry);
+        }
+
+        let rev_span = LEN / self.mode.revs_per_cycle();
+        let crk_tooth_angle = rev_span / self.crk.total_teeth;
+
+        self.validate::<LEN>(rev_span, crk_tooth_angle)?;
 
-        let crk_tooth_angle = self.crk.angle_per_tooth();
-        let angle_missing_teeth = self.crk.nr_of_missing_teeth() * self.crk.angle_per_tooth();
-        let mut crk_lvl = self.crk.first_level();
+        let mut idx_cam_edges = [0usize; MAX_CAM_TRACKS];
+        let mut cam_lvl = [Level::Low; MAX_CAM_TRACKS];
+        for (track, cam) in self.cam.iter().enumerate() {
+            cam_lvl[track] = cam.first_level;
+        }
 
         for (angle, val) in pt.iter_mut().enumerate() {
-            *val = if cam_lvl == Level::High {
-                *val | cam_msk
-            } else {
-                *val & !cam_msk
-            };
-            if idx_cam_edges < 20 {
-                if self.cam.ev_angles[idx_cam_edges] == angle as i16 {
-                    cam_lvl = !cam_lvl;
-                    idx_cam_edges += 1;
+            for track in 0..MAX_CAM_TRACKS {
+                *val = if cam_lvl[track] == Level::High {
+                    *val | cam_msk[track]
+                } else {
+                    *val & !cam_msk[track]
+                };
+                if idx_cam_edges[track] < 20
+                    && self.cam[track].ev_angles[idx_cam_edges[track]] == angle as i16
+                {
+                    cam_lvl[track] = !cam_lvl[track];
+                    idx_cam_edges[track] += 1;
                 }
             }
 
+            let crk_lvl = self.crk_level_at(angle, crk_tooth_angle, rev_span);
             *val = if crk_lvl == Level::High {
                 *val | crk_msk
             } else {
                 *val & !crk_msk
             };
-            if angle % ((crk_tooth_angle / 2) as usize) == 0 && angle != 0 {
-                if (angle % 3600) >= 3600 - angle_missing_teeth {
-                    crk_lvl = !self.crk.first_level();
-                } else {
-                    crk_lvl = !crk_lvl;
-                }
-            }
         }
 
-        let tdc_to_tdc = 7200 / self.nr_of_cyl.val();
-        pt[self.ref_to_tdc0 as usize] |= tdc_msk[0];
-
-        for cyl in 1..self.nr_of_cyl.val() {
-            pt[self.ref_to_tdc0 as usize + (cyl * tdc_to_tdc)] |= tdc_msk[cyl];
+        for (cyl, &angle) in self.tdc_angles.iter().enumerate() {
+            if angle >= 0 {
+                pt[angle as usize] |= tdc_msk[cyl];
+            }
         }
+
+        Ok(())
     }
 }
 
 pub static CFGS: [EngCfg; 1] = [EngCfg {
-    cam: Cam {
-        first_level: Level::High,
-        ev_angles: [
-            289, 389, 1189, 1289, 1489, 1589, 2089, 2189, 2689, 2789, 3889, 3989, 5089, 5189, 5689,
-            5789, 6289, 6389, 6589, 6689,
-        ],
-    },
-    crk: CrkType::Crk60m2Inv,
-    ref_to_tdc0: 658,
-    nr_of_cyl: CylNr::Cyl6,
+    cam: [
+        Cam {
+            first_level: Level::High,
+            ev_angles: [
+                289, 389, 1189, 1289, 1489, 1589, 2089, 2189, 2689, 2789, 3889, 3989, 5089, 5189,
+                5689, 5789, 6289, 6389, 6589, 6689,
+            ],
+        },
+        Cam::UNUSED,
+    ],
+    crk: CrkPattern::from_crk_type(&CrkType::Crk60m2Inv),
+    tdc_angles: EngCfg::even_tdc_angles(658, &CylNr::Cyl6, 3600, &OperationMode::FourStroke),
+    tooth_width: 500,
+    mode: OperationMode::FourStroke,
 }];
 
 #[cfg(test)]
 mod tests {
-    use crate::CFGS;
+    use crate::*;
     use rstest::rstest;
 
     #[rstest(
@@ -303,7 +545,7 @@ mod tests {
     fn tdc_test(angle: usize, tdc: usize, expected: bool) {
         let mut pls = [0u8; 7200];
 
-        CFGS[0].gen_pulse_train(&mut pls, 0x01, 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]);
+        CFGS[0].gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]).unwrap();
         assert_eq!(expected, pls[angle] & (1 << (tdc+2)) != 0)
     }
 
@@ -339,7 +581,7 @@ mod tests {
     fn cam_test(angle: usize, expected: bool) {
         let mut pls = [0u8; 7200];
 
-        CFGS[0].gen_pulse_train(&mut pls, 0x01, 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]);
+        CFGS[0].gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]).unwrap();
         assert_eq!(expected, pls[angle] & 1 != 0)
     }
 
@@ -358,7 +600,330 @@ mod tests {
     fn crk_gap_test(angle: usize, expected: bool) {
         let mut pls = [0u8; 7200];
 
-        CFGS[0].gen_pulse_train(&mut pls, 0x01, 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]);
+        CFGS[0].gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]).unwrap();
+        assert_eq!(expected, pls[angle] & 2 != 0);
+    }
+
+    #[rstest(
+        angle,
+        expected,
+        case(44 , false),
+        case(45 , true),
+        case(59 , true),
+        case(60 , false),
+        case(104, false),
+        case(105, true)
+    )]
+    fn tooth_width_test(angle: usize, expected: bool) {
+        let cfg = EngCfg {
+            cam: [Cam::UNUSED, Cam::UNUSED],
+            crk: CrkPattern::from_crk_type(&CrkType::Crk60m2Inv),
+            tdc_angles: [TDC_UNUSED; 6],
+            tooth_width: 250,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]).unwrap();
+        assert_eq!(expected, pls[angle] & 2 != 0);
+    }
+
+    #[rstest(
+        angle,
+        expected,
+        case(925 , true),
+        case(975 , false),
+        case(1050, false),
+        case(1150, false),
+        case(2250, false),
+        case(3450, false),
+        case(4525, true)
+    )]
+    fn multi_gap_test(angle: usize, expected: bool) {
+        let cfg = EngCfg {
+            cam: [Cam::UNUSED, Cam::UNUSED],
+            crk: CrkPattern {
+                total_teeth: 36,
+                gaps: [(10, 2), (22, 2), (34, 2), (0, 0)],
+                first_level: Level::High,
+            },
+            tdc_angles: [TDC_UNUSED; 6],
+            tooth_width: 500,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]).unwrap();
+        assert_eq!(expected, pls[angle] & 2 != 0);
+    }
+
+    #[rstest(
+        angle,
+        expected,
+        case(3481, false),
+        case(6961, true),
+        case(6970, true),
+        case(7081, true),
+        case(7199, true)
+    )]
+    fn two_stroke_crk_gap_test(angle: usize, expected: bool) {
+        let cfg = EngCfg {
+            cam: [Cam::UNUSED, Cam::UNUSED],
+            crk: CrkPattern::from_crk_type(&CrkType::Crk60m2Inv),
+            tdc_angles: EngCfg::even_tdc_angles(0, &CylNr::Cyl6, 7200, &OperationMode::TwoStroke),
+            tooth_width: 500,
+            mode: OperationMode::TwoStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]).unwrap();
+        assert_eq!(expected, pls[angle] & 2 != 0);
+    }
+
+    #[rstest(
+        tdc,
+        expected_angle,
+        case(0, 0),
+        case(1, 1200),
+        case(2, 2400),
+        case(3, 3600),
+        case(4, 4800),
+        case(5, 6000)
+    )]
+    fn two_stroke_even_tdc_angles_test(tdc: usize, expected_angle: i16) {
+        let angles = EngCfg::even_tdc_angles(0, &CylNr::Cyl6, 7200, &OperationMode::TwoStroke);
+        assert_eq!(expected_angle, angles[tdc]);
+    }
+
+    #[rstest(
+        angle,
+        tdc,
+        expected,
+        case(99 , 0, false),
+        case(100, 0, true),
+        case(250, 1, true),
+        case(400, 2, true)
+    )]
+    fn uneven_tdc_angles_test(angle: usize, tdc: usize, expected: bool) {
+        let cfg = EngCfg {
+            cam: [Cam::UNUSED, Cam::UNUSED],
+            crk: CrkPattern::from_crk_type(&CrkType::Crk60m2Inv),
+            tdc_angles: [100, 250, 400, TDC_UNUSED, TDC_UNUSED, TDC_UNUSED],
+            tooth_width: 500,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]).unwrap();
+        assert_eq!(expected, pls[angle] & (1 << (tdc + 2)) != 0);
+    }
+
+    #[rstest(
+        angle,
+        track,
+        expected,
+        case(0  , 0, true),
+        case(150, 0, false),
+        case(250, 0, true),
+        case(301, 1, true),
+        case(501, 1, false)
+    )]
+    fn second_cam_track_test(angle: usize, track: usize, expected: bool) {
+        let cfg = EngCfg {
+            cam: [
+                Cam {
+                    first_level: Level::High,
+                    ev_angles: {
+                        let mut a = [-1i16; 20];
+                        a[0] = 100;
+                        a[1] = 200;
+                        a
+                    },
+                },
+                Cam {
+                    first_level: Level::Low,
+                    ev_angles: {
+                        let mut a = [-1i16; 20];
+                        a[0] = 300;
+                        a[1] = 500;
+                        a
+                    },
+                },
+            ],
+            crk: CrkPattern::from_crk_type(&CrkType::Crk60m2Inv),
+            tdc_angles: [TDC_UNUSED; 6],
+            tooth_width: 500,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        cfg.gen_pulse_train(&mut pls, [0x01, 0x02], 0x04, [0x08, 0x10, 0x20, 0x40, 0x80, 0x00]).unwrap();
+        let bit = if track == 0 { 1 } else { 2 };
+        assert_eq!(expected, pls[angle] & bit != 0);
+    }
+
+    #[test]
+    fn tdc_out_of_range_error() {
+        let cfg = EngCfg {
+            cam: [Cam::UNUSED, Cam::UNUSED],
+            crk: CrkPattern::from_crk_type(&CrkType::Crk60m2Inv),
+            tdc_angles: [7200, TDC_UNUSED, TDC_UNUSED, TDC_UNUSED, TDC_UNUSED, TDC_UNUSED],
+            tooth_width: 500,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        assert_eq!(
+            Err(GenError::TdcOutOfRange { cyl: 0 }),
+            cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80])
+        );
+    }
+
+    #[test]
+    fn cam_edge_invalid_error() {
+        let cfg = EngCfg {
+            cam: [
+                Cam {
+                    first_level: Level::High,
+                    ev_angles: {
+                        let mut a = [-1i16; 20];
+                        a[0] = 500;
+                        a[1] = 400;
+                        a
+                    },
+                },
+                Cam::UNUSED,
+            ],
+            crk: CrkPattern::from_crk_type(&CrkType::Crk60m2Inv),
+            tdc_angles: [TDC_UNUSED; 6],
+            tooth_width: 500,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        assert_eq!(
+            Err(GenError::CamEdgeInvalid { track: 0, index: 1 }),
+            cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80])
+        );
+    }
+
+    #[test]
+    fn cam_edge_interspersed_unused_error() {
+        let cfg = EngCfg {
+            cam: [
+                Cam {
+                    first_level: Level::High,
+                    ev_angles: {
+                        let mut a = [-1i16; 20];
+                        a[0] = 100;
+                        a[1] = -1;
+                        a[2] = 300;
+                        a
+                    },
+                },
+                Cam::UNUSED,
+            ],
+            crk: CrkPattern::from_crk_type(&CrkType::Crk60m2Inv),
+            tdc_angles: [TDC_UNUSED; 6],
+            tooth_width: 500,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        assert_eq!(
+            Err(GenError::CamEdgeInvalid { track: 0, index: 2 }),
+            cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80])
+        );
+    }
+
+    #[test]
+    fn inconsistent_crk_geometry_error() {
+        let cfg = EngCfg {
+            cam: [Cam::UNUSED, Cam::UNUSED],
+            crk: CrkPattern {
+                total_teeth: 60,
+                gaps: [(59, 2), (0, 0), (0, 0), (0, 0)],
+                first_level: Level::High,
+            },
+            tdc_angles: [TDC_UNUSED; 6],
+            tooth_width: 500,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        assert_eq!(
+            Err(GenError::InconsistentCrkGeometry),
+            cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80])
+        );
+    }
+
+    #[test]
+    fn gen_pulse_train_ok() {
+        let mut pls = [0u8; 7200];
+
+        assert_eq!(
+            Ok(()),
+            CFGS[0].gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80])
+        );
+    }
+
+    #[test]
+    fn zero_total_teeth_error() {
+        let cfg = EngCfg {
+            cam: [Cam::UNUSED, Cam::UNUSED],
+            crk: CrkPattern {
+                total_teeth: 0,
+                gaps: [(0, 0); MAX_CRK_GAPS],
+                first_level: Level::High,
+            },
+            tdc_angles: [TDC_UNUSED; 6],
+            tooth_width: 500,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 7200];
+
+        assert_eq!(
+            Err(GenError::InconsistentCrkGeometry),
+            cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80])
+        );
+    }
+
+    #[rstest(rpm, case(0), case(6000))]
+    fn step_period_ns_never_panics(rpm: u32) {
+        EngCfg::step_period_ns(rpm, 7200, &OperationMode::FourStroke);
+    }
+
+    #[rstest(
+        mode,
+        expected,
+        case(OperationMode::FourStroke, 2777),
+        case(OperationMode::TwoStroke, 1388)
+    )]
+    fn step_period_ns_test(mode: OperationMode, expected: u32) {
+        assert_eq!(expected, EngCfg::step_period_ns(6000, 7200, &mode));
+    }
+
+    #[rstest(
+        angle,
+        expected,
+        case(14  , false),
+        case(15  , true),
+        case(29  , true),
+        case(30  , false),
+        case(1739, true),
+        case(1799, true)
+    )]
+    fn gen_pulse_train_at_len_3600_test(angle: usize, expected: bool) {
+        let cfg = EngCfg {
+            cam: [Cam::UNUSED, Cam::UNUSED],
+            crk: CrkPattern::from_crk_type(&CrkType::Crk60m2Inv),
+            tdc_angles: [50, TDC_UNUSED, TDC_UNUSED, TDC_UNUSED, TDC_UNUSED, TDC_UNUSED],
+            tooth_width: 500,
+            mode: OperationMode::FourStroke,
+        };
+        let mut pls = [0u8; 3600];
+
+        cfg.gen_pulse_train(&mut pls, [0x01, 0x00], 0x02, [0x04, 0x08, 0x10, 0x20, 0x40, 0x80]).unwrap();
         assert_eq!(expected, pls[angle] & 2 != 0);
     }
 }